@@ -0,0 +1,35 @@
+//! Intensity-independent anti-aliasing butterfly applied across the 31
+//! border regions between adjacent long-block subbands before hybrid
+//! synthesis (short blocks skip this entirely).
+
+/// The eight standard butterfly coefficients, expressed as the usual
+/// `ci` constants from which `cs`/`ca` are derived.
+const CI: [f32; 8] = [
+    -0.6, -0.535, -0.33, -0.185, -0.095, -0.041, -0.0142, -0.0037,
+];
+
+fn cs(i: usize) -> f32 {
+    1.0 / (1.0 + CI[i] * CI[i]).sqrt()
+}
+
+fn ca(i: usize) -> f32 {
+    CI[i] / (1.0 + CI[i] * CI[i]).sqrt()
+}
+
+/// Applies the anti-alias butterfly in place across subband
+/// boundaries `0..up_to` (a mixed block only aliases its long-block
+/// subbands, i.e. `up_to = 2`; a full long block uses `up_to = 32`).
+pub fn antialias(lines: &mut [[f32; 18]; 32], up_to: usize) {
+    for sb in 0..up_to.saturating_sub(1) {
+        for i in 0..8 {
+            let lo_idx = 17 - i;
+            let hi_idx = i;
+
+            let lo = lines[sb][lo_idx];
+            let hi = lines[sb + 1][hi_idx];
+
+            lines[sb][lo_idx] = lo * cs(i) - hi * ca(i);
+            lines[sb + 1][hi_idx] = hi * cs(i) + lo * ca(i);
+        }
+    }
+}
@@ -2,8 +2,15 @@
 
 use std::ops::*;
 
+use alias;
+use huffman;
+use id3::{self, Id3v2Tag};
+use imdct::{self, BlockType, Overlap};
 use result::*;
+use synthesis::SynthesisState;
+use tables;
 use utils::*;
+use vbr::{self, VbrInfo};
 
 /////////////////////////////////
 //// START PUBLIC INTERFACE
@@ -11,8 +18,55 @@ use utils::*;
 /// Main API entry
 pub struct Decoder<'a> {
     raw: &'a [u8],
+    /// The full audio stream (after ID3 tags are stripped), kept
+    /// around so `seek` can jump to an absolute byte offset rather
+    /// than only ever moving forward through `raw`.
+    audio_start: &'a [u8],
+    overlap: [Overlap; 2],
+    synth: [SynthesisState; 2],
+    prev_scalefac: [[i32; 21]; 2],
+    /// Bit reservoir: the tail of main data bytes emitted by recent
+    /// frames, since `main_data_begin` lets a frame's granule data
+    /// start earlier than its own main-data byte range.
+    reservoir: Vec<u8>,
+    /// The leading ID3v2 tag, if the stream started with one; stripped
+    /// from `raw` up front so frame search never scans its bytes.
+    id3v2: Option<Id3v2Tag>,
+    /// Whether the first frame has been checked for a Xing/VBRI tag
+    /// yet (it's only ever present in the very first audio frame).
+    vbr_probed: bool,
+    vbr: Option<VbrInfo>,
+    /// `(sample_rate, samples_per_frame, bitrate_kbps)` of the first
+    /// decoded frame, needed alongside `vbr` to turn a frame count
+    /// into a duration, and as a CBR fallback when seeking a stream
+    /// with no VBR header to supply a TOC.
+    first_frame_meta: Option<(u32, usize, u16)>,
+    /// The CRC-16 read from a protected frame's header, carried from
+    /// `read_header` over to `decode_frame` where the side info it
+    /// also covers becomes available. `None` for unprotected frames.
+    pending_crc: Option<u16>,
+    /// How to react when a protected frame's CRC doesn't match; see
+    /// [`CrcPolicy`]. Defaults to `Strict`.
+    crc_policy: CrcPolicy,
 }
 
+/// How `Decoder` reacts to a protected frame's CRC-16 failing.
+#[derive(PartialEq, Clone, Copy)]
+pub enum CrcPolicy {
+    /// Reject the frame with `Error::CrcMismatch`.
+    Strict,
+    /// Drop the frame and move on to the next one, as if it had never
+    /// been in the stream.
+    Skip,
+    /// Decode the frame's data anyway, ignoring the mismatch.
+    Ignore,
+}
+
+/// Upper bound on how much trailing main data the reservoir keeps
+/// around, comfortably above the largest `main_data_begin` (9 bits,
+/// so up to 511 bytes) a frame can reference.
+const MAX_RESERVOIR_LEN: usize = 2048;
+
 /// Decoded (logical) frame
 pub struct Frame {
     /// The decoded audio held by this frame. Channels are interleaved.
@@ -29,15 +83,138 @@ pub struct Frame {
 
 impl<'a> Decoder<'a> {
     pub fn next_frame(&mut self) -> Option<Result<Frame>> {
-        let header = self.read_header()?;
-        // self.decode_frame(&Header);
-        unimplemented!()
+        loop {
+            let header = match self.read_header()? {
+                Ok(hdr) => hdr,
+                Err(e) => return Some(Err(e)),
+            };
+
+            match self.decode_frame(header) {
+                Err(Error::CrcMismatch) if self.crc_policy == CrcPolicy::Skip => continue,
+                result => return Some(result),
+            }
+        }
+    }
+
+    /// Sets how a protected frame's CRC-16 mismatch is handled; see
+    /// [`CrcPolicy`]. Useful for lossy transports or slightly damaged
+    /// files where refusing to decode at all is worse than a few bad
+    /// samples.
+    pub fn set_crc_policy(&mut self, policy: CrcPolicy) {
+        self.crc_policy = policy;
+    }
+
+    /// The leading ID3v2 tag's header fields, if the stream had one.
+    pub fn id3v2(&self) -> Option<&Id3v2Tag> {
+        self.id3v2.as_ref()
+    }
+
+    /// The Xing/Info or VBRI VBR header parsed out of the first frame,
+    /// if the stream carried one. `None` before the first frame has
+    /// been decoded, or if the stream is plain CBR.
+    pub fn vbr_info(&self) -> Option<&VbrInfo> {
+        self.vbr.as_ref()
+    }
+
+    /// Total playback duration, derived from the VBR header's frame
+    /// count; `None` until the first frame is decoded, or if there is
+    /// no VBR header to read a frame count from.
+    pub fn duration_ms(&self) -> Option<u64> {
+        let vbr = self.vbr.as_ref()?;
+        let (sample_rate, samples_per_frame, _) = self.first_frame_meta?;
+
+        Some(vbr.total_frames as u64 * samples_per_frame as u64 * 1000 / sample_rate as u64)
+    }
+
+    /// Byte offset (from the start of the audio data) to seek to in
+    /// order to land near `target_ms` into playback, read from the VBR
+    /// header's TOC, or estimated linearly from its byte count when it
+    /// has no TOC. `None` if there is no VBR header.
+    pub fn seek_byte_offset_ms(&self, target_ms: u64) -> Option<u32> {
+        let duration = self.duration_ms()?;
+        let fraction = if duration == 0 { 0.0 } else { target_ms as f32 / duration as f32 };
+
+        self.vbr.as_ref().map(|v| v.byte_offset_for_fraction(fraction))
     }
 }
 
 impl<'a> From<&'a [u8]> for Decoder<'a> {
-    fn from(raw: &'a [u8]) -> Self {
-        Decoder { raw }
+    fn from(mut raw: &'a [u8]) -> Self {
+        let id3v2 = id3::strip_id3v2(&mut raw);
+        id3::strip_id3v1(&mut raw);
+
+        Decoder {
+            raw,
+            audio_start: raw,
+            overlap: [Overlap::new(), Overlap::new()],
+            synth: [SynthesisState::new(), SynthesisState::new()],
+            prev_scalefac: [[0; 21]; 2],
+            reservoir: Vec::with_capacity(MAX_RESERVOIR_LEN),
+            id3v2,
+            vbr_probed: false,
+            vbr: None,
+            first_frame_meta: None,
+            pending_crc: None,
+            crc_policy: CrcPolicy::Strict,
+        }
+    }
+}
+
+/// A uniform pull-based decode API: read the next packet, or seek to a
+/// playback position, without callers needing to know which container
+/// or VBR scheme (if any) backs the stream.
+pub trait AudioDecoder {
+    /// Pulls the next decoded packet, or `None` once the stream is
+    /// exhausted.
+    fn next_packet(&mut self) -> Option<Result<Frame>>;
+
+    /// Seeks to a byte position estimated for `ms` milliseconds into
+    /// playback; decoding resumes at the first frame sync found at or
+    /// after that position. A negative `ms` clamps to the beginning of
+    /// the stream.
+    fn seek(&mut self, ms: i64) -> Result<()>;
+}
+
+impl<'a> AudioDecoder for Decoder<'a> {
+    fn next_packet(&mut self) -> Option<Result<Frame>> {
+        self.next_frame()
+    }
+
+    fn seek(&mut self, ms: i64) -> Result<()> {
+        use self::Error::*;
+
+        let ms = ms.max(0) as u64;
+
+        if self.first_frame_meta.is_none() {
+            // Prime sample rate/bitrate metadata from the first frame
+            // before estimating anything; the decoded audio itself is
+            // discarded since we're about to jump elsewhere anyway.
+            self.next_frame();
+        }
+
+        let offset = match self.seek_byte_offset_ms(ms) {
+            Some(offset) => offset as usize,
+            None => {
+                let (_, _, bitrate_kbps) = self
+                    .first_frame_meta
+                    .ok_or(InvalidHeader("stream too short to seek"))?;
+
+                let bytes_per_sec = bitrate_kbps as usize * 1000 / 8;
+                ms as usize * bytes_per_sec / 1000
+            }
+        };
+
+        self.raw = &self.audio_start[offset.min(self.audio_start.len())..];
+
+        // A seek jumps to an unrelated point in the stream, so none of
+        // the cross-frame decode state (bit reservoir, MDCT overlap,
+        // synthesis history, scalefactor prediction) still applies.
+        self.reservoir.clear();
+        self.overlap = [Overlap::new(), Overlap::new()];
+        self.synth = [SynthesisState::new(), SynthesisState::new()];
+        self.prev_scalefac = [[0; 21]; 2];
+
+        Ok(())
     }
 }
 
@@ -54,6 +231,15 @@ enum Mode {
     Single,
 }
 
+#[derive(PartialEq)]
+#[repr(u8)]
+enum Version {
+    Mpeg25,
+    Reserved,
+    Mpeg2,
+    Mpeg1,
+}
+
 #[derive(PartialEq)]
 #[repr(u8)]
 enum Layer {
@@ -83,11 +269,8 @@ impl Header {
 
         let hdr = Header(raw);
 
-        if hdr.is_mpeg25() {
-            Err(UnsupportedFormat("MPEG-2.5"))
-        }
-        else if hdr.is_mpeg2() {
-            Err(UnsupportedFormat("MPEG-2"))
+        if hdr.version() == Version::Reserved {
+            Err(InvalidHeader("version 0b01 is reserved"))
         }
         else if hdr.layer() == Layer::Reserved {
             Err(InvalidHeader("layer 0x00 is reserved"))
@@ -106,16 +289,29 @@ impl Header {
         }
     }
 
+    /// MPEG version, read from the 2-bit `ID` field at bits 19-20.
+    fn version(&self) -> Version {
+        use self::Version::*;
+
+        match self.0.bit_range(19..21) {
+            0b00 => Mpeg25,
+            0b01 => Reserved,
+            0b10 => Mpeg2,
+            0b11 => Mpeg1,
+            _ => unreachable!(),
+        }
+    }
+
     fn is_mpeg25(&self) -> bool {
-        (self.0.bit_range(20..21)) == 0
+        self.version() == Version::Mpeg25
     }
 
     fn is_mpeg2(&self) -> bool {
-        self.0.bit_range(19..20) == 1
+        self.version() == Version::Mpeg2
     }
 
     fn is_mpeg1(&self) -> bool {
-        !self.is_mpeg25() && !self.is_mpeg2()
+        self.version() == Version::Mpeg1
     }
 
     fn layer(&self) -> Layer {
@@ -139,7 +335,7 @@ impl Header {
     }
 
     fn bitrate_kbps(&self) -> u16 {
-        static BITRATES: [[u16; 15]; 3] = [
+        static BITRATES_MPEG1: [[u16; 15]; 3] = [
             // L1
             [
                 0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320,
@@ -157,23 +353,46 @@ impl Header {
             ],
         ];
 
+        // MPEG-2/2.5 (LSF) bitrates only distinguish Layer I from
+        // Layers II/III.
+        static BITRATES_LSF: [[u16; 15]; 2] = [
+            // L1
+            [
+                0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176,
+                192, 224, 256,
+            ],
+            // L2 / L3
+            [
+                0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128,
+                144, 160,
+            ],
+        ];
+
         let bitrate_idx = self.bitrate_index();
-        let layer_idx = self.layer() as usize - 1;
 
-        BITRATES[layer_idx][bitrate_idx]
+        if self.is_mpeg1() {
+            let layer_idx = self.layer() as usize - 1;
+            BITRATES_MPEG1[layer_idx][bitrate_idx]
+        } else {
+            let layer_idx = if self.layer() == Layer::L1 { 0 } else { 1 };
+            BITRATES_LSF[layer_idx][bitrate_idx]
+        }
     }
 
     fn sampling_rate_index(&self) -> usize {
-        // TODO: support MPEG 2 and 2.5
-        debug_assert!(self.is_mpeg1());
-
         self.0.bit_range(10..12) as usize
     }
 
     fn sampling_rate_hz(&self) -> u32 {
-        static RATES: [u32; 3] = [44100, 48000, 32000];
+        static RATES_MPEG1: [u32; 3] = [44100, 48000, 32000];
+        static RATES_MPEG2: [u32; 3] = [22050, 24000, 16000];
+        static RATES_MPEG25: [u32; 3] = [11025, 12000, 8000];
 
-        RATES[self.sampling_rate_index()]
+        match self.version() {
+            Version::Mpeg1 => RATES_MPEG1[self.sampling_rate_index()],
+            Version::Mpeg2 => RATES_MPEG2[self.sampling_rate_index()],
+            Version::Mpeg25 | Version::Reserved => RATES_MPEG25[self.sampling_rate_index()],
+        }
     }
 
     fn padding(&self) -> bool {
@@ -195,11 +414,28 @@ impl Header {
         }
     }
 
+    fn channels(&self) -> usize {
+        if self.channel_mode() == Mode::Single { 1 } else { 2 }
+    }
+
     // TODO: use an enum
     fn mode_ext(&self) -> u8 {
         self.0.bit_range(4..6) as u8
     }
 
+    /// Whether this Joint-stereo frame uses MS stereo (`mode_ext` bit 0).
+    /// Only meaningful when `channel_mode() == Mode::Joint`.
+    fn ms_stereo(&self) -> bool {
+        self.mode_ext() & 0b01 != 0
+    }
+
+    /// Whether this Joint-stereo frame uses intensity stereo (`mode_ext`
+    /// bit 1). Only meaningful when `channel_mode() == Mode::Joint`.
+    #[allow(unused)]
+    fn intensity_stereo(&self) -> bool {
+        self.mode_ext() & 0b10 != 0
+    }
+
     fn copyright_bit(&self) -> bool {
         self.0.bit_range(3..4) != 0
     }
@@ -217,64 +453,734 @@ impl Header {
             _ => unreachable!()
         }
     }
+
+    /// Total size of this frame in bytes, header included, as derived
+    /// from the bitrate/sample rate/padding fields.
+    fn frame_length_bytes(&self) -> usize {
+        let slots_per_ms = if self.is_mpeg1() { 144 } else { 72 };
+        let padding = if self.padding() { 1 } else { 0 };
+
+        (slots_per_ms * self.bitrate_kbps() as usize * 1000 / self.sampling_rate_hz() as usize) + padding
+    }
+
+    /// Number of granules per channel: MPEG-1 Layer III always has
+    /// two, MPEG-2/2.5 halve the frame (and granule count) instead.
+    fn granule_count(&self) -> usize {
+        if self.is_mpeg1() { 2 } else { 1 }
+    }
+
+    fn side_info_len(&self) -> usize {
+        match (self.is_mpeg1(), self.channels()) {
+            (true, 1) => 17,
+            (true, _) => 32,
+            (false, 1) => 9,
+            (false, _) => 17,
+        }
+    }
+
+    fn sample_rate_table_index(&self) -> usize {
+        self.sampling_rate_index()
+    }
+}
+
+/// Per-granule side info, one per channel per granule.
+struct GranuleInfo {
+    part2_3_length: u32,
+    big_values: u32,
+    global_gain: u32,
+    scalefac_compress: u32,
+    window_switching: bool,
+    block_type: u32,
+    mixed_block: bool,
+    table_select: [u32; 3],
+    subblock_gain: [u32; 3],
+    region0_count: u32,
+    region1_count: u32,
+    preflag: bool,
+    scalefac_scale: bool,
+    count1table_select: bool,
+}
+
+struct SideInfo {
+    main_data_begin: u32,
+    scfsi: [[bool; 4]; 2],
+    granules: Vec<[GranuleInfo; 2]>,
 }
 
+const PRETAB: [i32; 21] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 3, 3, 3, 2,
+];
+
 // private methods
 impl<'a> Decoder<'a> {
     fn read_header(&mut self) -> Option<Result<Header>> {
-        if self.raw.len() < 4 {
-            return None;
-        }
-
-        let mut raw = 
-            (self.raw[0] as u32) << 24
-                | (self.raw[1] as u32) << 16
-                | (self.raw[2] as u32) << 8
-                | (self.raw[3] as u32) << 0;
-
-        self.raw.advance(4);
+        let mut raw = self.raw.read_u32_be()?;
 
         fn starts_with_syncword(raw: u32) -> bool {
             (raw.bit_range(20..32) | 1) as u16 == 0xfff0
         }
 
-        if !starts_with_syncword(raw) {
-            for byte in self.raw.iter().map(|b| *b) {
-                raw <<= 8;
-                raw |= byte as u32;
+        // Scanning for a sync word has to actually consume the bytes
+        // it skips over, or `self.raw` is left misaligned with the
+        // header it just recovered and everything read after it (side
+        // info, main data) comes from the wrong offset.
+        while !starts_with_syncword(raw) {
+            let byte = self.raw.split_checked(1)?[0];
+            raw = (raw << 8) | byte as u32;
+        }
 
-                if starts_with_syncword(raw) {
-                    break;
-                }
+        match Header::from_raw(raw) {
+            Err(e) => Some(Err(e)),
+            Ok(hdr) => {
+                self.pending_crc = if hdr.is_protected() {
+                    Some(self.raw.read_u16_be()?)
+                } else {
+                    None
+                };
+
+                Some(Ok(hdr))
             }
+        }
+    }
+
+    /// Runs the Layer III decode pipeline for one frame: side info,
+    /// main data (scalefactors + Huffman-coded spectrum), hybrid
+    /// synthesis and the polyphase filter bank.
+    fn decode_frame(&mut self, header: Header) -> Result<Frame> {
+        use self::Error::*;
+
+        if header.layer() != Layer::L3 {
+            return Err(UnsupportedFormat("only Layer III is decoded"));
+        }
 
-            if !starts_with_syncword(raw) {
-                return None;
+        let channels = header.channels();
+        let side_info_len = header.side_info_len();
+
+        let frame_body_start = self.raw;
+
+        let side_info_bytes = self
+            .raw
+            .split_checked(side_info_len)
+            .ok_or(InvalidHeader("truncated side info"))?;
+
+        if let Some(expected) = self.pending_crc.take() {
+            let header_tail = [(header.0 >> 8) as u8, header.0 as u8];
+            let crc = crc16_update(crc16_update(0xffff, &header_tail), side_info_bytes);
+
+            // `Skip` also surfaces as `CrcMismatch` here; `next_frame`
+            // is the one that turns that into moving on to the next
+            // frame instead of returning the error.
+            if crc != expected && self.crc_policy != CrcPolicy::Ignore {
+                return Err(CrcMismatch);
             }
         }
 
-        match Header::from_raw(raw) {
-            Err(e) => Some(Err(e)),
-            Ok(hdr) => {
-                if hdr.is_protected() {
-                    if self.raw.len() < 2 {
-                        return None;
+        let side_info = read_side_info(side_info_bytes, &header);
+
+        let crc_len = if header.is_protected() { 2 } else { 0 };
+        let frame_len = header.frame_length_bytes();
+        let consumed_so_far = 4 + crc_len + side_info_len;
+        let main_data_len = frame_len.saturating_sub(consumed_so_far);
+
+        let main_data = self
+            .raw
+            .split_checked(main_data_len)
+            .ok_or(InvalidHeader("truncated main data"))?;
+
+        if !self.vbr_probed {
+            self.vbr_probed = true;
+
+            let frame_body_len = (side_info_len + main_data_len).min(frame_body_start.len());
+            self.vbr = vbr::parse(&frame_body_start[..frame_body_len], side_info_len, crc_len);
+
+            self.first_frame_meta = Some((
+                header.sampling_rate_hz(),
+                header.granule_count() * 576,
+                header.bitrate_kbps(),
+            ));
+        }
+
+        // `main_data_begin` back-references into the bit reservoir: it
+        // counts how many bytes before the data we just read this
+        // frame's granules actually start at, so prior frames may
+        // still be leaving bytes for us to pick up here.
+        let prev_len = self.reservoir.len();
+        self.reservoir.extend_from_slice(main_data);
+
+        if side_info.main_data_begin as usize > prev_len {
+            return Err(InvalidHeader("main_data_begin reaches before the start of the stream"));
+        }
+
+        let start = prev_len.saturating_sub(side_info.main_data_begin as usize);
+        let granule_data = &self.reservoir[start..];
+
+        let mut pcm = Vec::with_capacity(header.granule_count() * 576 * channels);
+
+        let sr_idx = header.sample_rate_table_index();
+        let mut reader = BitReader::new(granule_data);
+
+        for (gr_idx, granule_pair) in side_info.granules.iter().enumerate() {
+            let mut granule_lines: Vec<[[f32; 18]; 32]> = Vec::with_capacity(channels);
+
+            for ch in 0..channels {
+                let gr = &granule_pair[ch];
+                let part_start_bit = reader.bits_consumed();
+
+                let scalefac = read_scalefactors(
+                    &mut reader,
+                    gr,
+                    side_info.scfsi[ch],
+                    gr_idx,
+                    header.is_mpeg1(),
+                    &mut self.prev_scalefac[ch],
+                );
+
+                let flat = read_spectrum(&mut reader, gr, sr_idx, &scalefac);
+                granule_lines.push(group_into_subbands(&flat, gr, sr_idx));
+
+                // `part2_3_length` is the authoritative length (in bits)
+                // of this channel's scalefactors + Huffman data; resync
+                // to it rather than trusting the Huffman reader to land
+                // exactly there, so any bit-count drift can't bleed into
+                // the next channel or granule.
+                reader.seek_to(part_start_bit + gr.part2_3_length as usize);
+            }
+
+            // `mode_ext` selects MS and/or intensity stereo independently
+            // for Joint-stereo frames; only the MS half is handled here.
+            // TODO: intensity stereo (mode_ext bit 1) is not decoded yet.
+            if channels == 2 && header.channel_mode() == Mode::Joint && header.ms_stereo() {
+                apply_mid_side(&mut granule_lines);
+            }
+
+            let mut channel_pcm: Vec<Vec<i16>> = Vec::with_capacity(channels);
+
+            for ch in 0..channels {
+                let block_type = BlockType::from_bits(granule_pair[ch].block_type);
+                let mixed = granule_pair[ch].mixed_block;
+
+                if granule_pair[ch].window_switching && block_type == BlockType::Short {
+                    // Short blocks are not aliased; mixed blocks only
+                    // alias their two long-block subbands.
+                    if mixed {
+                        alias::antialias(&mut granule_lines[ch], 2);
                     }
+                } else {
+                    alias::antialias(&mut granule_lines[ch], 32);
+                }
 
-                    let crc = (self.raw[0] as u16) << 8
-                        | (self.raw[1] as u16) << 0;
+                let synthed = imdct::hybrid_synthesis(
+                    &mut self.overlap[ch],
+                    &granule_lines[ch],
+                    block_type,
+                    mixed,
+                );
 
-                    self.raw.advance(2);
+                let mut samples = Vec::with_capacity(576);
+                for i in 0..18 {
+                    let mut subband_sample = [0.0f32; 32];
+                    for sb in 0..32 {
+                        subband_sample[sb] = synthed[sb][i];
 
-                    // TODO: CRC check
+                        // Odd subbands carry a sign flip every other time
+                        // sample (frequency inversion), required so the
+                        // polyphase synthesis filter bank reconstructs
+                        // the right spectrum rather than a mirrored one.
+                        if sb % 2 == 1 && i % 2 == 1 {
+                            subband_sample[sb] = -subband_sample[sb];
+                        }
+                    }
+
+                    self.synth[ch].synth_block(&subband_sample, &mut samples);
                 }
 
-                Some(Ok(hdr))
+                channel_pcm.push(samples);
+            }
+
+            for i in 0..576 {
+                for ch in 0..channels {
+                    pcm.push(channel_pcm[ch][i]);
+                }
+            }
+        }
+
+        if self.reservoir.len() > MAX_RESERVOIR_LEN {
+            let excess = self.reservoir.len() - MAX_RESERVOIR_LEN;
+            self.reservoir.drain(..excess);
+        }
+
+        Ok(Frame {
+            data: pcm,
+            sample_rate: header.sampling_rate_hz() as i32,
+            channels,
+            layer: 3,
+            bitrate_kbps: header.bitrate_kbps() as i32,
+        })
+    }
+}
+
+/// Feeds `data` through the CRC-16 used to protect Layer III headers
+/// (poly `0x8005`, MSB-first, no final XOR), continuing from a
+/// previous `crc` accumulator so the header and side info can be
+/// checked as one running checksum.
+fn crc16_update(crc: u16, data: &[u8]) -> u16 {
+    let mut crc = crc;
+
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+fn read_side_info(data: &[u8], header: &Header) -> SideInfo {
+    let mut reader = BitReader::new(data);
+    let channels = header.channels();
+    let granule_count = header.granule_count();
+
+    let main_data_begin = reader.get_bits(if header.is_mpeg1() { 9 } else { 8 });
+
+    let private_bits = match (header.is_mpeg1(), channels) {
+        (true, 1) => 5,
+        (true, _) => 3,
+        (false, 1) => 1,
+        (false, _) => 2,
+    };
+    reader.get_bits(private_bits);
+
+    // MPEG-2/2.5 (LSF) side info drops scfsi entirely: each granule
+    // always carries its own full set of scalefactors.
+    let mut scfsi = [[false; 4]; 2];
+    if header.is_mpeg1() {
+        for ch in 0..channels {
+            for band in 0..4 {
+                scfsi[ch][band] = reader.get_bit() != 0;
+            }
+        }
+    }
+
+    let mut granules = Vec::with_capacity(granule_count);
+
+    for _ in 0..granule_count {
+        let mut pair: Vec<GranuleInfo> = Vec::with_capacity(channels.max(1));
+
+        for _ in 0..channels {
+            let part2_3_length = reader.get_bits(12);
+            let big_values = reader.get_bits(9);
+            let global_gain = reader.get_bits(8);
+            let scalefac_compress = reader.get_bits(if header.is_mpeg1() { 4 } else { 9 });
+            let window_switching = reader.get_bit() != 0;
+
+            let mut block_type = 0;
+            let mut mixed_block = false;
+            let mut table_select = [0u32; 3];
+            let mut subblock_gain = [0u32; 3];
+            let mut region0_count = 0;
+            let mut region1_count = 0;
+
+            if window_switching {
+                block_type = reader.get_bits(2);
+                mixed_block = reader.get_bit() != 0;
+
+                table_select[0] = reader.get_bits(5);
+                table_select[1] = reader.get_bits(5);
+
+                subblock_gain[0] = reader.get_bits(3);
+                subblock_gain[1] = reader.get_bits(3);
+                subblock_gain[2] = reader.get_bits(3);
+
+                region0_count = if block_type == 2 && !mixed_block { 8 } else { 7 };
+                region1_count = 20 - region0_count;
+            } else {
+                table_select[0] = reader.get_bits(5);
+                table_select[1] = reader.get_bits(5);
+                table_select[2] = reader.get_bits(5);
+
+                region0_count = reader.get_bits(4);
+                region1_count = reader.get_bits(3);
+            }
+
+            // MPEG-2/2.5 side info carries no preflag bit; it is implied
+            // by a `scalefac_compress` of 500 or more instead.
+            let preflag = if header.is_mpeg1() {
+                reader.get_bit() != 0
+            } else {
+                scalefac_compress >= 500
+            };
+            let scalefac_scale = reader.get_bit() != 0;
+            let count1table_select = reader.get_bit() != 0;
+
+            pair.push(GranuleInfo {
+                part2_3_length,
+                big_values,
+                global_gain,
+                scalefac_compress,
+                window_switching,
+                block_type,
+                mixed_block,
+                table_select,
+                subblock_gain,
+                region0_count,
+                region1_count,
+                preflag,
+                scalefac_scale,
+                count1table_select,
+            });
+        }
+
+        // Mono streams still occupy the per-channel slot; duplicate it
+        // so indexing by `[ch]` stays uniform.
+        while pair.len() < 2 {
+            let clone = GranuleInfo {
+                part2_3_length: pair[0].part2_3_length,
+                big_values: pair[0].big_values,
+                global_gain: pair[0].global_gain,
+                scalefac_compress: pair[0].scalefac_compress,
+                window_switching: pair[0].window_switching,
+                block_type: pair[0].block_type,
+                mixed_block: pair[0].mixed_block,
+                table_select: pair[0].table_select,
+                subblock_gain: pair[0].subblock_gain,
+                region0_count: pair[0].region0_count,
+                region1_count: pair[0].region1_count,
+                preflag: pair[0].preflag,
+                scalefac_scale: pair[0].scalefac_scale,
+                count1table_select: pair[0].count1table_select,
+            };
+            pair.push(clone);
+        }
+
+        let arr: [GranuleInfo; 2] = [pair.remove(0), pair.remove(0)];
+        granules.push(arr);
+    }
+
+    SideInfo { main_data_begin, scfsi, granules }
+}
+
+/// MPEG-2/2.5 (LSF) `scalefac_compress` packs four independent slen
+/// widths into its 9 bits across three disjoint ranges, rather than
+/// selecting a row out of MPEG-1's small 4-bit lookup table.
+fn lsf_scalefac_slen(scalefac_compress: u32) -> [u32; 4] {
+    let c = scalefac_compress;
+
+    if c < 400 {
+        [(c >> 4) / 5, (c >> 4) % 5, (c % 16) >> 2, c % 4]
+    } else if c < 500 {
+        let c = c - 400;
+        [(c >> 2) / 5, (c >> 2) % 5, c % 4, 0]
+    } else {
+        let c = c - 500;
+        [c / 3, c % 3, 0, 0]
+    }
+}
+
+fn read_scalefactors(
+    reader: &mut BitReader,
+    gr: &GranuleInfo,
+    scfsi: [bool; 4],
+    gr_idx: usize,
+    is_mpeg1: bool,
+    prev: &mut [i32; 21],
+) -> [i32; 21] {
+    // MPEG-1 only ever varies slen across two groups (bands 0-10 share
+    // `slen1`, bands 11-20 share `slen2`); MPEG-2/2.5's wider field
+    // gives all four groups an independent width.
+    let widths = if is_mpeg1 {
+        let (slen1, slen2) = tables::SCALEFAC_SLEN[gr.scalefac_compress as usize];
+        [slen1, slen1, slen2, slen2]
+    } else {
+        lsf_scalefac_slen(gr.scalefac_compress)
+    };
+
+    let group_bounds = [0usize, 6, 11, 16, 21];
+
+    let mut sf = [0i32; 21];
+
+    for g in 0..4 {
+        // MPEG-2/2.5 side info carries no scfsi: every granule writes
+        // its own full set of scalefactors, so there's never a prior
+        // granule's values to copy.
+        let copy = is_mpeg1 && gr_idx == 1 && scfsi[g];
+
+        for b in group_bounds[g]..group_bounds[g + 1] {
+            sf[b] = if copy { prev[b] } else { reader.get_bits(widths[g]) as i32 };
+        }
+    }
+
+    *prev = sf;
+    sf
+}
+
+fn xy_table_dims(idx: u32) -> (u8, u8) {
+    match idx {
+        1 => (1, 1),
+        2 | 3 => (2, 2),
+        5 | 6 => (3, 3),
+        7 | 8 | 9 => (5, 5),
+        10 | 11 | 12 => (7, 7),
+        13 | 15 => (15, 15),
+        16..=31 => (15, 15),
+        _ => (0, 0),
+    }
+}
+
+fn sfb_of_line(sfb_bounds: &[usize; 23], line: usize) -> usize {
+    for sfb in 0..21 {
+        if line < sfb_bounds[sfb + 1] {
+            return sfb;
+        }
+    }
+    20
+}
+
+/// Short-block spectral lines are coded sfb-major, window-second,
+/// frequency-minor (see [`group_into_subbands`]'s reordering), so a
+/// flat Huffman-decode position maps to a `(sfb, window)` pair rather
+/// than the single `sfb` a long block would use.
+fn sfb_window_of_line(sfb_bounds: &[usize; 14], line: usize) -> (usize, usize) {
+    let mut pos = 0usize;
+
+    for sfb in 0..13 {
+        let width = sfb_bounds[sfb + 1] - sfb_bounds[sfb];
+
+        for window in 0..3 {
+            if line < pos + width {
+                return (sfb, window);
+            }
+            pos += width;
+        }
+    }
+
+    (12, 2)
+}
+
+/// Requantizes one decoded `(is)` Huffman value into a spectral sample.
+/// `window` is `Some(w)` for the three windows of a (non-mixed) short
+/// block: each window's `subblock_gain` lowers the global gain, and the
+/// long-block `preflag`/`PRETAB` boost is not applied.
+fn requantize(is: i32, sfb: usize, gr: &GranuleInfo, scalefac: &[i32; 21], window: Option<usize>) -> f32 {
+    if is == 0 {
+        return 0.0;
+    }
+
+    let sign = if is < 0 { -1.0 } else { 1.0 };
+    let magnitude = (is.abs() as f32).powf(4.0 / 3.0);
+
+    let subblock_boost = match window {
+        Some(w) => 8.0 * gr.subblock_gain[w] as f32,
+        None => 0.0,
+    };
+    let gain = 2f32.powf(0.25 * (gr.global_gain as f32 - 210.0 - subblock_boost));
+
+    let scale_step = if gr.scalefac_scale { 1.0 } else { 0.5 };
+    let pre = if window.is_none() && gr.preflag { PRETAB[sfb] } else { 0 };
+    let scale = 2f32.powf(-scale_step * (scalefac[sfb] + pre) as f32);
+
+    sign * magnitude * gain * scale
+}
+
+/// Huffman-decodes the full 576-line spectrum for one granule/channel,
+/// applying requantization as each line comes off the wire.
+fn read_spectrum(
+    reader: &mut BitReader,
+    gr: &GranuleInfo,
+    sr_idx: usize,
+    scalefac: &[i32; 21],
+) -> [f32; 576] {
+    let mut out = [0.0f32; 576];
+    let sfb_bounds = &tables::SFB_LONG[sr_idx];
+    let sfb_bounds_short = &tables::SFB_SHORT[sr_idx];
+    let is_short = gr.window_switching && BlockType::from_bits(gr.block_type) == BlockType::Short && !gr.mixed_block;
+
+    let big_values_samples = (gr.big_values * 2) as usize;
+
+    // For (non-mixed) short blocks the region split isn't a long-block
+    // scalefactor-band boundary at all: the spec fixes region0 to
+    // empty and folds everything into region1 (table_select[2] is
+    // correspondingly never read from short-block side info). Mixed
+    // blocks keep the long-block split below for their long-coded low
+    // frequencies.
+    let (r0_end, r1_end) = if is_short {
+        (0, big_values_samples)
+    } else {
+        let r0_end_band = (gr.region0_count + 1) as usize;
+        let r1_end_band = r0_end_band + (gr.region1_count + 1) as usize;
+
+        (
+            sfb_bounds[r0_end_band.min(22)].min(big_values_samples),
+            sfb_bounds[r1_end_band.min(22)].min(big_values_samples),
+        )
+    };
+
+    // Each region uses at most one table, so build the (up to three)
+    // distinct tables once rather than per decoded symbol.
+    let region_tables: Vec<Option<huffman::HuffmanTable>> = gr
+        .table_select
+        .iter()
+        .map(|&idx| {
+            if idx == 0 {
+                None
+            } else {
+                let (xmax, ymax) = xy_table_dims(idx);
+                Some(huffman::build_xy_table(idx, xmax, ymax))
+            }
+        })
+        .collect();
+
+    let mut pos = 0;
+    while pos < big_values_samples {
+        let region = if pos < r0_end { 0 } else if pos < r1_end { 1 } else { 2 };
+        let table_idx = gr.table_select[region];
+
+        let (mut x, mut y) = match &region_tables[region] {
+            None => (0u32, 0u32),
+            Some(table) => {
+                let (x, y) = table.decode(reader);
+                (x as u32, y as u32)
+            }
+        };
+
+        let linbits = tables::HUFFMAN_LINBITS[table_idx as usize] as u32;
+
+        if linbits > 0 && x == xy_table_dims(table_idx).0 as u32 {
+            x += reader.get_bits(linbits);
+        }
+        if linbits > 0 && y == xy_table_dims(table_idx).1 as u32 {
+            y += reader.get_bits(linbits);
+        }
+
+        let mut xi = x as i32;
+        if x > 0 && reader.get_bit() != 0 {
+            xi = -xi;
+        }
+        let mut yi = y as i32;
+        if y > 0 && reader.get_bit() != 0 {
+            yi = -yi;
+        }
+
+        if pos < 576 {
+            let (sfb, window) = if is_short {
+                let (sfb, w) = sfb_window_of_line(sfb_bounds_short, pos);
+                (sfb, Some(w))
+            } else {
+                (sfb_of_line(sfb_bounds, pos), None)
+            };
+            out[pos] = requantize(xi, sfb, gr, scalefac, window);
+        }
+        if pos + 1 < 576 {
+            let (sfb, window) = if is_short {
+                let (sfb, w) = sfb_window_of_line(sfb_bounds_short, pos + 1);
+                (sfb, Some(w))
+            } else {
+                (sfb_of_line(sfb_bounds, pos + 1), None)
+            };
+            out[pos + 1] = requantize(yi, sfb, gr, scalefac, window);
+        }
+
+        pos += 2;
+    }
+
+    // The remaining lines (up to 576) are coded four at a time via the
+    // `count1` quad tables; `count1table_select` picks table A or B.
+    let quad = huffman::build_quad_table(gr.count1table_select);
+    while pos < 576 {
+        let (sym, _) = quad.decode(reader);
+
+        let magnitudes = [
+            (sym >> 3) & 1,
+            (sym >> 2) & 1,
+            (sym >> 1) & 1,
+            sym & 1,
+        ];
+
+        let mut values = [0i32; 4];
+        for (v, &mag) in values.iter_mut().zip(magnitudes.iter()) {
+            *v = if mag != 0 {
+                if reader.get_bit() != 0 { -1 } else { 1 }
+            } else {
+                0
+            };
+        }
+
+        for (i, &v) in values.iter().enumerate() {
+            if pos + i < 576 {
+                let (sfb, window) = if is_short {
+                    let (sfb, w) = sfb_window_of_line(sfb_bounds_short, pos + i);
+                    (sfb, Some(w))
+                } else {
+                    (sfb_of_line(sfb_bounds, pos + i), None)
+                };
+                out[pos + i] = requantize(v, sfb, gr, scalefac, window);
+            }
+        }
+
+        pos += 4;
+
+        if reader.bits_left() == 0 {
+            break;
+        }
+    }
+
+    out
+}
+
+fn group_into_subbands(flat: &[f32; 576], gr: &GranuleInfo, sr_idx: usize) -> [[f32; 18]; 32] {
+    let mut out = [[0.0f32; 18]; 32];
+
+    if gr.window_switching && BlockType::from_bits(gr.block_type) == BlockType::Short && !gr.mixed_block {
+        // Reorder from (sfb, window, k) order into (subband, window,
+        // line) order expected by hybrid synthesis.
+        let bounds = &tables::SFB_SHORT[sr_idx];
+        let mut raw_pos = 0usize;
+
+        for sfb in 0..13 {
+            let width = bounds[sfb + 1] - bounds[sfb];
+
+            for window in 0..3 {
+                for k in 0..width {
+                    let p = bounds[sfb] + k;
+                    let sb = p / 6;
+                    let line = p % 6;
+
+                    if sb < 32 {
+                        out[sb][window * 6 + line] = flat[raw_pos];
+                    }
+                    raw_pos += 1;
+                }
             }
         }
+    } else {
+        for i in 0..576 {
+            out[i / 18][i % 18] = flat[i];
+        }
     }
+
+    out
 }
 
+fn apply_mid_side(granule_lines: &mut Vec<[[f32; 18]; 32]>) {
+    if granule_lines.len() != 2 {
+        return;
+    }
 
+    const INV_SQRT_2: f32 = std::f32::consts::FRAC_1_SQRT_2;
 
+    let (left, right) = granule_lines.split_at_mut(1);
+    for sb in 0..32 {
+        for i in 0..18 {
+            let mid = left[0][sb][i];
+            let side = right[0][sb][i];
 
+            left[0][sb][i] = (mid + side) * INV_SQRT_2;
+            right[0][sb][i] = (mid - side) * INV_SQRT_2;
+        }
+    }
+}
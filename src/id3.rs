@@ -0,0 +1,63 @@
+//! Leading ID3v2 and trailing ID3v1 tag detection, so frame search
+//! doesn't have to rely on luck to skip past tag bytes (and doesn't
+//! mistake a stray `0xFF` inside tag data for a sync word).
+
+/// Parsed ID3v2 header fields.
+#[derive(Debug)]
+pub struct Id3v2Tag {
+    pub major_version: u8,
+    pub revision: u8,
+    pub flags: u8,
+    /// Tag size in bytes, not counting the 10-byte header (or the
+    /// optional 10-byte footer).
+    pub size: u32,
+}
+
+const ID3V1_LEN: usize = 128;
+
+/// If `raw` starts with an ID3v2 header, parses it and advances `raw`
+/// past the whole tag (header, body and footer if present).
+pub fn strip_id3v2(raw: &mut &[u8]) -> Option<Id3v2Tag> {
+    if raw.len() < 10 || &raw[0..3] != b"ID3" {
+        return None;
+    }
+
+    let tag = Id3v2Tag {
+        major_version: raw[3],
+        revision: raw[4],
+        flags: raw[5],
+        size: synchsafe32(raw[6], raw[7], raw[8], raw[9]),
+    };
+
+    let footer_present = tag.flags & 0x10 != 0;
+    let mut skip = 10 + tag.size as usize;
+    if footer_present {
+        skip += 10;
+    }
+
+    *raw = &raw[skip.min(raw.len())..];
+
+    Some(tag)
+}
+
+/// If `raw` ends with a 128-byte ID3v1 `"TAG"` block, trims it off so
+/// it's never scanned for a frame sync word.
+pub fn strip_id3v1(raw: &mut &[u8]) {
+    if raw.len() < ID3V1_LEN {
+        return;
+    }
+
+    let tag_start = raw.len() - ID3V1_LEN;
+    if &raw[tag_start..tag_start + 3] == b"TAG" {
+        *raw = &raw[..tag_start];
+    }
+}
+
+/// Decodes a synchsafe 28-bit integer: each byte only contributes its
+/// low 7 bits, so no encoded size byte can ever look like a sync word.
+fn synchsafe32(b0: u8, b1: u8, b2: u8, b3: u8) -> u32 {
+    ((b0 & 0x7f) as u32) << 21
+        | ((b1 & 0x7f) as u32) << 14
+        | ((b2 & 0x7f) as u32) << 7
+        | (b3 & 0x7f) as u32
+}
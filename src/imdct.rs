@@ -0,0 +1,123 @@
+//! Hybrid synthesis: the per-subband IMDCT (+ windowing) and the
+//! overlap-add stage that turns two granules' worth of frequency-line
+//! blocks into the 32x18 time-domain subband samples fed to the
+//! polyphase synthesis filter bank.
+
+use std::f32::consts::PI;
+
+/// Block shape as carried by the side info's `block_type` field.
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlockType {
+    Long,
+    Start,
+    Short,
+    Stop,
+}
+
+impl BlockType {
+    pub fn from_bits(bits: u32) -> Self {
+        match bits {
+            0 => BlockType::Long,
+            1 => BlockType::Start,
+            2 => BlockType::Short,
+            3 => BlockType::Stop,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Per-channel state carried across granules/frames so the IMDCT
+/// overlap-add can blend each block with the tail of the previous one.
+pub struct Overlap {
+    tail: [[f32; 18]; 32],
+}
+
+impl Overlap {
+    pub fn new() -> Self {
+        Overlap { tail: [[0.0; 18]; 32] }
+    }
+}
+
+fn long_window(i: usize) -> f32 {
+    (PI / 36.0 * (i as f32 + 0.5)).sin()
+}
+
+fn short_window(i: usize) -> f32 {
+    (PI / 12.0 * (i as f32 + 0.5)).sin()
+}
+
+/// 18-point-in, 36-point-out IMDCT, windowed for a long block.
+fn imdct_long(input: &[f32; 18]) -> [f32; 36] {
+    let mut out = [0.0f32; 36];
+
+    for (i, slot) in out.iter_mut().enumerate() {
+        let mut acc = 0.0f32;
+        for (k, &x) in input.iter().enumerate() {
+            acc += x * ((PI / 72.0) * (2.0 * i as f32 + 1.0 + 18.0) * (2.0 * k as f32 + 1.0)).cos();
+        }
+        *slot = acc * long_window(i);
+    }
+
+    out
+}
+
+/// 6-point-in, 12-point-out IMDCT, windowed for a single short window.
+fn imdct_short(input: &[f32; 6]) -> [f32; 12] {
+    let mut out = [0.0f32; 12];
+
+    for (i, slot) in out.iter_mut().enumerate() {
+        let mut acc = 0.0f32;
+        for (k, &x) in input.iter().enumerate() {
+            acc += x * ((PI / 24.0) * (2.0 * i as f32 + 1.0 + 6.0) * (2.0 * k as f32 + 1.0)).cos();
+        }
+        *slot = acc * short_window(i);
+    }
+
+    out
+}
+
+/// Runs hybrid synthesis for one granule's worth of subband lines,
+/// producing the 32x18 time samples handed to the synthesis filter
+/// bank. `lines` holds, per subband, the 18 dequantized/reordered
+/// frequency lines for this granule.
+pub fn hybrid_synthesis(
+    overlap: &mut Overlap,
+    lines: &[[f32; 18]; 32],
+    block_type: BlockType,
+    mixed_block: bool,
+) -> [[f32; 18]; 32] {
+    let mut out = [[0.0f32; 18]; 32];
+
+    for sb in 0..32 {
+        let use_long = block_type != BlockType::Short || (mixed_block && sb < 2);
+
+        let windowed: [f32; 36] = if use_long {
+            imdct_long(&lines[sb])
+        } else {
+            // Split the granule's 18 lines into three groups of 6 and
+            // run a short IMDCT on each, interleaving the windows the
+            // way the standard lays subbands 0..36 out in time order.
+            let mut combined = [0.0f32; 36];
+            for w in 0..3 {
+                let mut chunk = [0.0f32; 6];
+                for k in 0..6 {
+                    chunk[k] = lines[sb][w * 6 + k];
+                }
+                let windowed = imdct_short(&chunk);
+                for i in 0..12 {
+                    combined[w * 12 + i] += windowed[i];
+                }
+            }
+            combined
+        };
+
+        for i in 0..18 {
+            out[sb][i] = windowed[i] + overlap.tail[sb][i];
+        }
+        for i in 0..18 {
+            overlap.tail[sb][i] = windowed[18 + i];
+        }
+    }
+
+    out
+}
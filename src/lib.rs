@@ -0,0 +1,17 @@
+//! `rustymp3` — a small, dependency-free MPEG-1/2 Layer III decoder.
+
+mod alias;
+mod decoder;
+mod huffman;
+mod id3;
+mod imdct;
+mod result;
+mod synthesis;
+mod tables;
+mod utils;
+mod vbr;
+
+pub use decoder::{AudioDecoder, CrcPolicy, Decoder, Frame, Header};
+pub use id3::Id3v2Tag;
+pub use result::{Error, Result};
+pub use vbr::VbrInfo;
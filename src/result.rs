@@ -6,6 +6,9 @@ pub type Result<T> = result::Result<T, Error>;
 pub enum Error {
     UnsupportedFormat(&'static str),
     InvalidHeader(&'static str),
+    /// The frame's stored CRC-16 didn't match the one computed over
+    /// its protected header bits and side info.
+    CrcMismatch,
 }
 
 impl fmt::Display for Error {
@@ -19,6 +22,7 @@ impl fmt::Display for Error {
             InvalidHeader(err) => {
                 write!(f, "invalid header: {}", err)
             }
+            CrcMismatch => write!(f, "CRC check failed"),
         }
     }
 }
@@ -0,0 +1,48 @@
+//! Constant tables used by the Layer III decoder: scalefactor band
+//! boundaries, MPEG-1 scalefactor widths and Huffman `linbits`, plus
+//! the Huffman code entry type.
+
+/// Scalefactor band boundaries for long blocks, indexed by
+/// `sampling_rate_index` (0 = 44100Hz, 1 = 48000Hz, 2 = 32000Hz for
+/// MPEG-1). 23 boundaries describe 22 bands.
+pub static SFB_LONG: [[usize; 23]; 3] = [
+    [0, 4, 8, 12, 16, 20, 24, 30, 36, 44, 52, 62, 74, 90, 110, 134, 162, 196, 238, 288, 342, 418, 576],
+    [0, 4, 8, 12, 16, 20, 24, 30, 36, 42, 50, 60, 72, 88, 106, 128, 156, 190, 230, 276, 330, 384, 576],
+    [0, 4, 8, 12, 16, 20, 24, 30, 36, 44, 54, 66, 82, 102, 126, 156, 194, 240, 296, 364, 448, 550, 576],
+];
+
+/// Scalefactor band boundaries for short blocks (per window), same
+/// indexing as [`SFB_LONG`]. 14 boundaries describe 13 bands.
+pub static SFB_SHORT: [[usize; 14]; 3] = [
+    [0, 4, 8, 12, 16, 22, 30, 40, 52, 66, 84, 106, 136, 192],
+    [0, 4, 8, 12, 16, 22, 28, 38, 50, 64, 80, 100, 126, 192],
+    [0, 4, 8, 12, 16, 22, 30, 42, 58, 78, 104, 138, 180, 192],
+];
+
+/// The full MPEG-1 `(slen1, slen2)` table, selected by the 4-bit
+/// `scalefac_compress` field (values 0..=15): `slen1` bits per
+/// scalefactor in bands 0-10, `slen2` in bands 11-20. MPEG-2/2.5's
+/// wider 9-bit field uses a different scheme entirely (see
+/// `lsf_scalefac_slen` in decoder.rs) rather than indexing this table.
+pub static SCALEFAC_SLEN: [(u32, u32); 16] = [
+    (0, 0), (0, 1), (0, 2), (0, 3),
+    (3, 0), (1, 1), (1, 2), (1, 3),
+    (2, 1), (2, 2), (2, 3), (3, 1),
+    (3, 2), (3, 3), (4, 2), (4, 3),
+];
+
+/// Per-table `linbits` (extra escape bits appended to out-of-range
+/// `big_values` codes), indexed by Huffman table number 0..=31.
+pub static HUFFMAN_LINBITS: [u8; 32] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 2, 3, 4, 6, 8, 10, 13, 4, 5, 6, 7, 8, 9, 11, 13,
+];
+
+/// One entry of a Huffman code table: a `(length, code)` pair maps to
+/// the decoded `(x, y)` value pair.
+pub struct HuffCode {
+    pub len: u8,
+    pub code: u16,
+    pub x: u8,
+    pub y: u8,
+}
@@ -1,8 +1,18 @@
 use std::mem;
 use std::ops::*;
 
-pub trait SliceExt {
-    fn advance(&mut self, n: usize);
+/// Checked, panic-free cursor operations over a borrowed byte slice.
+pub trait SliceExt<'a> {
+    /// Splits `at` bytes off the front of `self`, advancing `self`
+    /// past them and returning them. Returns `None` and leaves `self`
+    /// unchanged if fewer than `at` bytes are left.
+    fn split_checked(&mut self, at: usize) -> Option<&'a [u8]>;
+
+    /// Reads and consumes a big-endian `u16` from the front.
+    fn read_u16_be(&mut self) -> Option<u16>;
+
+    /// Reads and consumes a big-endian `u32` from the front.
+    fn read_u32_be(&mut self) -> Option<u32>;
 }
 
 pub trait UnsignedInteger {}
@@ -75,15 +85,30 @@ where
     }
 }
 
-impl<'a, T> SliceExt for &'a [T] {
-    fn advance(&mut self, n: usize) {
-        *self = unsafe { mem::transmute(&self[n..]) };
+impl<'a> SliceExt<'a> for &'a [u8] {
+    fn split_checked(&mut self, at: usize) -> Option<&'a [u8]> {
+        if at > self.len() {
+            return None;
+        }
+
+        let (head, tail) = self.split_at(at);
+        *self = tail;
+        Some(head)
+    }
+
+    fn read_u16_be(&mut self) -> Option<u16> {
+        let bytes = self.split_checked(2)?;
+        Some((bytes[0] as u16) << 8 | bytes[1] as u16)
     }
-}
 
-impl<'a, T> SliceExt for &'a mut [T] {
-    fn advance(&mut self, n: usize) {
-        *self = unsafe { mem::transmute(&mut self[n..]) };
+    fn read_u32_be(&mut self) -> Option<u32> {
+        let bytes = self.split_checked(4)?;
+        Some(
+            (bytes[0] as u32) << 24
+                | (bytes[1] as u32) << 16
+                | (bytes[2] as u32) << 8
+                | bytes[3] as u32,
+        )
     }
 }
 
@@ -99,6 +124,66 @@ impl UIntBitsRng for u32 {}
 impl UIntBitsRng for u64 {}
 impl UIntBitsRng for usize {}
 
+/// A MSB-first bit-level cursor over a byte slice, used to pull the
+/// variable-width fields out of Layer III side info and main data
+/// (scalefactors, Huffman codewords, ...).
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    /// Number of bits already consumed from the front of `data`.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    /// Bits available before the cursor runs past the end of `data`;
+    /// 0 once it has.
+    pub fn bits_left(&self) -> usize {
+        (self.data.len() * 8).saturating_sub(self.pos)
+    }
+
+    pub fn bits_consumed(&self) -> usize {
+        self.pos
+    }
+
+    /// Jumps the cursor to an absolute bit position (clamped to just
+    /// past the end of `data`), so a caller can resync to a known
+    /// boundary (e.g. a granule's `part2_3_length`) instead of trusting
+    /// variable-length decoding to land exactly on it.
+    pub fn seek_to(&mut self, pos: usize) {
+        self.pos = pos.min(self.data.len() * 8);
+    }
+
+    /// Reads `n` bits (0..=32) MSB-first, returning 0-padding once the
+    /// underlying slice is exhausted rather than panicking, since main
+    /// data lengths are only known approximately ahead of time.
+    pub fn get_bits(&mut self, n: u32) -> u32 {
+        let mut out = 0u32;
+
+        for _ in 0..n {
+            let byte = self.pos / 8;
+            let bit_in_byte = 7 - (self.pos % 8);
+
+            let bit = match self.data.get(byte) {
+                Some(b) => (b >> bit_in_byte) & 1,
+                None => 0,
+            };
+
+            out = (out << 1) | bit as u32;
+            self.pos += 1;
+        }
+
+        out
+    }
+
+    /// Reads a single bit, see [`BitReader::get_bits`].
+    pub fn get_bit(&mut self) -> u32 {
+        self.get_bits(1)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,19 +216,30 @@ mod tests {
     }
 
     #[test]
-    fn slice_advance() {
+    fn split_checked() {
         let mut slice = &[1, 2, 3, 4][..];
 
-        slice.advance(0);
+        assert_eq!(slice.split_checked(0), Some(&[][..]));
         assert_eq!(slice, &[1, 2, 3, 4][..]);
 
-        slice.advance(2);
+        assert_eq!(slice.split_checked(2), Some(&[1, 2][..]));
         assert_eq!(slice, &[3, 4][..]);
     }
 
     #[test]
-    #[should_panic]
-    fn slice_shilf_fail() {
-        (&[1u32][..]).advance(2)
+    fn split_checked_too_far() {
+        let mut slice = &[1u8][..];
+
+        assert_eq!(slice.split_checked(2), None);
+        assert_eq!(slice, &[1u8][..]);
+    }
+
+    #[test]
+    fn read_be_ints() {
+        let mut slice = &[0x01, 0x02, 0x03, 0x04, 0x05][..];
+
+        assert_eq!(slice.read_u16_be(), Some(0x0102));
+        assert_eq!(slice.read_u32_be(), None);
+        assert_eq!(slice, &[0x03, 0x04, 0x05][..]);
     }
 }
@@ -0,0 +1,111 @@
+//! Xing/Info and VBRI VBR header detection. Encoders that use a
+//! variable bitrate stash the true frame/byte count and a seek table
+//! in the first audio frame (in place of real audio data), since
+//! those can't be derived from the bitrate field the way they can for
+//! a CBR stream.
+
+/// Parsed VBR metadata header, from either a Xing/Info or a VBRI tag.
+pub struct VbrInfo {
+    pub total_frames: u32,
+    pub total_bytes: u32,
+    /// A seek table mapping the `i`-th percent of the stream's
+    /// duration to the `toc[i]`-th 256th of `total_bytes`. Empty if
+    /// the tag didn't carry one.
+    pub toc: Vec<u8>,
+}
+
+impl VbrInfo {
+    /// Translates a playback position (`0.0..=1.0` through the whole
+    /// stream) into a byte offset, using the TOC when there is one and
+    /// falling back to a linear estimate otherwise.
+    pub fn byte_offset_for_fraction(&self, fraction: f32) -> u32 {
+        let fraction = fraction.max(0.0).min(1.0);
+
+        if self.toc.is_empty() {
+            return (fraction * self.total_bytes as f32) as u32;
+        }
+
+        let index = ((fraction * 100.0) as usize).min(self.toc.len() - 1);
+        let percent_bytes = self.toc[index] as f32 / 256.0;
+
+        (percent_bytes * self.total_bytes as f32) as u32
+    }
+}
+
+/// Looks for a Xing/Info header right after the side info, or a VBRI
+/// header at its fixed offset, within `frame_body` (everything in the
+/// frame after the 4-byte sync header/CRC). `side_info_len` is how far
+/// into `frame_body` the side info runs; `crc_len` is how much of the
+/// 4-byte-header-relative offsets `frame_body` has already had
+/// stripped off the front (0, or 2 for a CRC-protected frame).
+pub fn parse(frame_body: &[u8], side_info_len: usize, crc_len: usize) -> Option<VbrInfo> {
+    parse_xing(frame_body.get(side_info_len..)?).or_else(|| parse_vbri(frame_body, crc_len))
+}
+
+fn parse_xing(data: &[u8]) -> Option<VbrInfo> {
+    if data.len() < 8 || (&data[0..4] != b"Xing" && &data[0..4] != b"Info") {
+        return None;
+    }
+
+    let flags = be32(&data[4..8]);
+    let mut pos = 8;
+
+    let mut total_frames = 0;
+    let mut total_bytes = 0;
+    let mut toc = Vec::new();
+
+    if flags & 0x1 != 0 && data.len() >= pos + 4 {
+        total_frames = be32(&data[pos..pos + 4]);
+        pos += 4;
+    }
+    if flags & 0x2 != 0 && data.len() >= pos + 4 {
+        total_bytes = be32(&data[pos..pos + 4]);
+        pos += 4;
+    }
+    if flags & 0x4 != 0 {
+        if data.len() >= pos + 100 {
+            toc = data[pos..pos + 100].to_vec();
+        }
+        pos += 100;
+    }
+    // The quality indicator (flags & 0x8, one more 4-byte field) isn't
+    // needed for duration/seeking and is left unread.
+    let _ = pos;
+
+    Some(VbrInfo { total_frames, total_bytes, toc })
+}
+
+fn parse_vbri(frame_body: &[u8], crc_len: usize) -> Option<VbrInfo> {
+    // The VBRI tag sits 32 bytes past the *4-byte sync header*,
+    // regardless of whether the frame carries a CRC. `frame_body` has
+    // already had the header and any CRC stripped off, so the offset
+    // from here is 32 minus whatever `crc_len` already accounted for.
+    const OFFSET_FROM_HEADER: usize = 32;
+
+    let offset = OFFSET_FROM_HEADER.saturating_sub(crc_len);
+    let data = frame_body.get(offset..)?;
+    if data.len() < 26 || &data[0..4] != b"VBRI" {
+        return None;
+    }
+
+    let total_bytes = be32(&data[10..14]);
+    let total_frames = be32(&data[14..18]);
+    let toc_entries = be16(&data[18..20]) as usize;
+    let toc_entry_bytes = be16(&data[22..24]) as usize;
+
+    let toc_len = toc_entries * toc_entry_bytes;
+    let toc = data
+        .get(26..26 + toc_len)
+        .map(|slice| slice.to_vec())
+        .unwrap_or_default();
+
+    Some(VbrInfo { total_frames, total_bytes, toc })
+}
+
+fn be32(b: &[u8]) -> u32 {
+    (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32
+}
+
+fn be16(b: &[u8]) -> u16 {
+    (b[0] as u16) << 8 | b[1] as u16
+}